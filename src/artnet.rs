@@ -0,0 +1,388 @@
+// Art-Net packet parsing.
+// Reference: https://art-net.org.uk/resources/art-net-specification/
+
+use std::fmt;
+use std::net::Ipv4Addr;
+
+/// The fixed 8-byte identifier that begins every Art-Net packet.
+pub const ID: &[u8; 8] = b"Art-Net\0";
+
+/// OpOutput / OpDmx - DMX data packet.
+pub const OP_DMX: u16 = 0x5000;
+/// OpPoll - controller discovery request.
+pub const OP_POLL: u16 = 0x2000;
+/// OpPollReply - node discovery response.
+pub const OP_POLL_REPLY: u16 = 0x2100;
+/// OpRdm - RDM request/response carried over Art-Net.
+pub const OP_RDM: u16 = 0x8300;
+
+/// Lowest protocol version (ArtNet II) this parser accepts.
+pub const MIN_PROTOCOL_VERSION: u8 = 14;
+
+/// UDP port all Art-Net traffic, including ArtPollReply, is sent on.
+pub const PORT: u16 = 6454;
+
+/// Offset of the 2-byte, little-endian-on-the-wire OpCode field.
+const OPCODE_OFFSET: usize = 8;
+/// Size of the fixed ArtDmx header, before the variable-length DMX data.
+const HEADER_LEN: usize = 18;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Packet is shorter than the fixed header of the packet type being parsed
+    /// (ArtDmx or ArtRdm).
+    TooShort,
+    /// Packet does not start with the `Art-Net\0` identifier.
+    BadId,
+    /// OpCode is not the one the parser handles (`OpDmx` for `parse_artdmx`,
+    /// `OpRdm` for `parse_artrdm`).
+    UnsupportedOpcode(u16),
+    /// Protocol version is older than `MIN_PROTOCOL_VERSION`.
+    UnsupportedVersion(u8),
+    /// Declared length field is longer than the data present in the packet.
+    LengthMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::TooShort => write!(f, "packet shorter than the fixed header"),
+            Error::BadId => write!(f, "missing \"Art-Net\\0\" identifier"),
+            Error::UnsupportedOpcode(op) => write!(f, "unsupported opcode: {:#06x}", op),
+            Error::UnsupportedVersion(lo) => write!(f, "unsupported protocol version: {}", lo),
+            Error::LengthMismatch => write!(f, "length field exceeds packet data"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Returns the OpCode of a packet, or `None` if it is too short to contain one
+/// or does not start with the `Art-Net\0` identifier.
+fn opcode(buf: &[u8]) -> Option<u16> {
+    if buf.len() < OPCODE_OFFSET + 2 || &buf[0..8] != ID {
+        return None;
+    }
+    Some(u16::from_le_bytes([buf[OPCODE_OFFSET], buf[OPCODE_OFFSET + 1]]))
+}
+
+/// Parsed ArtDmx header, with `data` borrowed from the original packet.
+pub struct ArtDmx<'a> {
+    /// Sequence number, 0 means sequencing is disabled for this source.
+    pub sequence: u8,
+    pub physical: u8,
+    /// 15-bit universe address: `net << 8 | sub_uni`.
+    pub universe: u16,
+    pub data: &'a [u8],
+}
+
+/// Validates and parses an ArtDmx packet out of a raw UDP datagram.
+pub fn parse_artdmx(buf: &[u8]) -> Result<ArtDmx<'_>, Error> {
+    if buf.len() < HEADER_LEN {
+        return Err(Error::TooShort);
+    }
+    if &buf[0..8] != ID {
+        return Err(Error::BadId);
+    }
+    let opcode = u16::from_le_bytes([buf[OPCODE_OFFSET], buf[OPCODE_OFFSET + 1]]);
+    if opcode != OP_DMX {
+        return Err(Error::UnsupportedOpcode(opcode));
+    }
+    let prot_ver_hi = buf[10];
+    let prot_ver_lo = buf[11];
+    if prot_ver_hi != 0 || prot_ver_lo < MIN_PROTOCOL_VERSION {
+        return Err(Error::UnsupportedVersion(prot_ver_lo));
+    }
+    let sequence = buf[12];
+    let physical = buf[13];
+    let sub_uni = buf[14];
+    let net = buf[15];
+    let universe = (net as u16) << 8 | sub_uni as u16;
+    let length = u16::from_be_bytes([buf[16], buf[17]]) as usize;
+    let data = &buf[HEADER_LEN..];
+    if length > data.len() {
+        return Err(Error::LengthMismatch);
+    }
+    Ok(ArtDmx {
+        sequence,
+        physical,
+        universe,
+        data: &data[..length],
+    })
+}
+
+/// Checks whether a packet is an ArtPoll discovery request. ArtPoll carries
+/// only behaviour flags we don't act on, so there is no need to fully parse it.
+pub fn is_artpoll(buf: &[u8]) -> bool {
+    opcode(buf) == Some(OP_POLL)
+}
+
+/// Describes this node for the purpose of building an ArtPollReply.
+pub struct NodeInfo<'a> {
+    pub ip: Ipv4Addr,
+    pub mac: [u8; 6],
+    pub short_name: &'a str,
+    pub long_name: &'a str,
+    /// Configured output universe addresses, in port order.
+    pub universes: &'a [u16],
+}
+
+/// Number of output ports described by a single ArtPollReply.
+pub const MAX_REPLY_PORTS: usize = 4;
+
+/// Builds a single 239-byte ArtPollReply packet describing this node and up
+/// to the first `MAX_REPLY_PORTS` of its configured output universes (the
+/// format only carries one 4-port "board" per reply). NetSwitch/SubSwitch are
+/// shared by every port in that board, so they are derived from the first
+/// configured universe; any port whose universe falls outside that Net/Sub-Net
+/// is reported with the wrong address (a limitation of the format, not just
+/// this implementation - a node would need one ArtPollReply per Net/Sub-Net).
+pub fn build_poll_reply(info: &NodeInfo) -> Vec<u8> {
+    let num_ports = info.universes.len().min(MAX_REPLY_PORTS);
+    let (net_switch, sub_switch) = match info.universes.first() {
+        Some(u) => (((u >> 8) & 0x7f) as u8, ((u >> 4) & 0x0f) as u8),
+        None => (0, 0),
+    };
+
+    let mut pkt = Vec::with_capacity(239);
+    pkt.extend_from_slice(ID);
+    pkt.extend_from_slice(&OP_POLL_REPLY.to_le_bytes());
+    pkt.extend_from_slice(&info.ip.octets());
+    pkt.extend_from_slice(&PORT.to_le_bytes());
+    pkt.extend_from_slice(&[0, 0]); // VersInfoH/L
+    pkt.extend_from_slice(&[net_switch, sub_switch]); // NetSwitch/SubSwitch
+    pkt.extend_from_slice(&[0xf0, 0x7f]); // OemLo/Hi - unregistered/prototype range
+    pkt.push(0); // UbeaVersion
+    pkt.push(0xd2); // Status1: indicators normal, address set by this program, RDM supported
+    pkt.extend_from_slice(&[0xf0, 0x7f]); // EstaManLo/Hi - unregistered/prototype range
+    pkt.extend_from_slice(&pascal_string(info.short_name, 18));
+    pkt.extend_from_slice(&pascal_string(info.long_name, 64));
+    pkt.extend_from_slice(&[0u8; 64]); // NodeReport - unused
+    pkt.extend_from_slice(&[0, num_ports as u8]); // NumPortsHi/Lo
+    pkt.extend_from_slice(&port_field(num_ports, 0x80)); // PortTypes: output, DMX512
+    pkt.extend_from_slice(&port_field(num_ports, 0x00)); // GoodInput - we have no inputs
+    pkt.extend_from_slice(&port_field(num_ports, 0x80)); // GoodOutput: data is being transmitted
+    pkt.extend_from_slice(&port_field(num_ports, 0x00)); // SwIn - unused
+    let mut sw_out = [0u8; MAX_REPLY_PORTS];
+    for (slot, universe) in sw_out.iter_mut().zip(info.universes.iter()) {
+        *slot = (*universe & 0x0f) as u8;
+    }
+    pkt.extend_from_slice(&sw_out);
+    pkt.push(0); // SwVideo
+    pkt.push(0); // SwMacro
+    pkt.push(0); // SwRemote
+    pkt.extend_from_slice(&[0u8; 3]); // Spare
+    pkt.push(0); // Style: StNode
+    pkt.extend_from_slice(&info.mac);
+    pkt.extend_from_slice(&info.ip.octets()); // BindIp
+    pkt.push(0); // BindIndex
+    pkt.push(0); // Status2
+    pkt.extend_from_slice(&[0u8; 26]); // Filler
+    pkt
+}
+
+fn pascal_string(s: &str, len: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    let bytes = s.as_bytes();
+    let n = bytes.len().min(len - 1);
+    buf[..n].copy_from_slice(&bytes[..n]);
+    buf
+}
+
+fn port_field(num_ports: usize, value: u8) -> [u8; MAX_REPLY_PORTS] {
+    let mut field = [0u8; MAX_REPLY_PORTS];
+    for slot in field.iter_mut().take(num_ports) {
+        *slot = value;
+    }
+    field
+}
+
+/// Fixed ArtRdm header length, before the variable-length RDM message.
+const RDM_HEADER_LEN: usize = 24;
+
+/// Parsed ArtRdm envelope, with the raw RDM message (start code through
+/// checksum) borrowed from the original packet.
+pub struct ArtRdm<'a> {
+    /// 15-bit universe address: `net << 8 | sub_uni`.
+    pub universe: u16,
+    pub rdm_packet: &'a [u8],
+}
+
+/// Validates and parses an ArtRdm packet out of a raw UDP datagram.
+pub fn parse_artrdm(buf: &[u8]) -> Result<ArtRdm<'_>, Error> {
+    match opcode(buf) {
+        Some(OP_RDM) => {}
+        Some(op) => return Err(Error::UnsupportedOpcode(op)),
+        None => return Err(Error::BadId),
+    }
+    if buf.len() < RDM_HEADER_LEN {
+        return Err(Error::TooShort);
+    }
+    let prot_ver_hi = buf[10];
+    let prot_ver_lo = buf[11];
+    if prot_ver_hi != 0 || prot_ver_lo < MIN_PROTOCOL_VERSION {
+        return Err(Error::UnsupportedVersion(prot_ver_lo));
+    }
+    let net = buf[21];
+    let address = buf[23];
+    let universe = (net as u16) << 8 | address as u16;
+    Ok(ArtRdm {
+        universe,
+        rdm_packet: &buf[RDM_HEADER_LEN..],
+    })
+}
+
+/// Builds an ArtRdm packet carrying `rdm_packet` (a full, already-encoded RDM
+/// message) for the given universe, used to relay a responder's reply back
+/// to the controller that sent the request.
+pub fn build_artrdm(universe: u16, rdm_packet: &[u8]) -> Vec<u8> {
+    let mut pkt = Vec::with_capacity(RDM_HEADER_LEN + rdm_packet.len());
+    pkt.extend_from_slice(ID);
+    pkt.extend_from_slice(&OP_RDM.to_le_bytes());
+    pkt.push(0); // ProtVerHi
+    pkt.push(MIN_PROTOCOL_VERSION); // ProtVerLo
+    pkt.push(0x01); // RdmVer
+    pkt.push(0); // Filler2
+    pkt.extend_from_slice(&[0u8; 7]); // Spare1-7
+    pkt.push((universe >> 8) as u8); // Net
+    pkt.push(0); // Command: process
+    pkt.push((universe & 0xff) as u8); // Address: SubNet<<4 | Universe
+    pkt.extend_from_slice(rdm_packet);
+    pkt
+}
+
+/// Compares an incoming sequence number against the last one accepted for a
+/// universe, per the Art-Net sequencing rules: 0 disables ordering entirely,
+/// otherwise the incoming value must be newer modulo 256 (wrap-around aware).
+pub fn is_newer_sequence(last: u8, incoming: u8) -> bool {
+    if incoming == 0 {
+        return true;
+    }
+    if last == 0 {
+        return true;
+    }
+    (1..128).contains(&incoming.wrapping_sub(last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artdmx_packet(prot_ver_hi: u8, prot_ver_lo: u8, length: u16, data_len: usize) -> Vec<u8> {
+        let mut pkt = Vec::new();
+        pkt.extend_from_slice(ID);
+        pkt.extend_from_slice(&OP_DMX.to_le_bytes());
+        pkt.extend_from_slice(&[prot_ver_hi, prot_ver_lo]);
+        pkt.extend_from_slice(&[0, 0, 0, 0]); // Sequence, Physical, SubUni, Net
+        pkt.extend_from_slice(&length.to_be_bytes());
+        pkt.resize(pkt.len() + data_len, 0u8);
+        pkt
+    }
+
+    #[test]
+    fn parses_well_formed_artdmx() {
+        let pkt = artdmx_packet(0, MIN_PROTOCOL_VERSION, 3, 3);
+        let dmx = parse_artdmx(&pkt).unwrap();
+        assert_eq!(dmx.data.len(), 3);
+    }
+
+    #[test]
+    fn rejects_artdmx_shorter_than_header() {
+        let pkt = artdmx_packet(0, MIN_PROTOCOL_VERSION, 0, 0);
+        assert!(matches!(
+            parse_artdmx(&pkt[..HEADER_LEN - 1]),
+            Err(Error::TooShort)
+        ));
+    }
+
+    #[test]
+    fn rejects_artdmx_length_longer_than_data() {
+        // A forged Length field longer than the data actually present must be
+        // rejected instead of panicking when the data slice is truncated to it.
+        let pkt = artdmx_packet(0, MIN_PROTOCOL_VERSION, 10, 3);
+        assert!(matches!(parse_artdmx(&pkt), Err(Error::LengthMismatch)));
+    }
+
+    #[test]
+    fn rejects_artdmx_unsupported_protocol_version() {
+        let pkt = artdmx_packet(1, MIN_PROTOCOL_VERSION, 0, 0);
+        assert!(matches!(
+            parse_artdmx(&pkt),
+            Err(Error::UnsupportedVersion(_))
+        ));
+        let pkt = artdmx_packet(0, MIN_PROTOCOL_VERSION - 1, 0, 0);
+        assert!(matches!(
+            parse_artdmx(&pkt),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_artdmx_missing_id() {
+        let mut pkt = artdmx_packet(0, MIN_PROTOCOL_VERSION, 0, 0);
+        pkt[0] = b'x';
+        assert!(matches!(parse_artdmx(&pkt), Err(Error::BadId)));
+    }
+
+    fn artrdm_packet(rdm_packet: &[u8]) -> Vec<u8> {
+        let mut pkt = Vec::new();
+        pkt.extend_from_slice(ID);
+        pkt.extend_from_slice(&OP_RDM.to_le_bytes());
+        pkt.push(0); // ProtVerHi
+        pkt.push(MIN_PROTOCOL_VERSION); // ProtVerLo
+        pkt.push(0x01); // RdmVer
+        pkt.push(0); // Filler2
+        pkt.extend_from_slice(&[0u8; 7]); // Spare1-7
+        pkt.push(0); // Net
+        pkt.push(0); // Command
+        pkt.push(0); // Address
+        pkt.extend_from_slice(rdm_packet);
+        pkt
+    }
+
+    #[test]
+    fn parses_well_formed_artrdm() {
+        let pkt = artrdm_packet(&[0xCC, 0x01]);
+        let artrdm = parse_artrdm(&pkt).unwrap();
+        assert_eq!(artrdm.rdm_packet, &[0xCC, 0x01]);
+    }
+
+    #[test]
+    fn rejects_artrdm_shorter_than_header() {
+        let pkt = artrdm_packet(&[]);
+        assert!(matches!(
+            parse_artrdm(&pkt[..RDM_HEADER_LEN - 1]),
+            Err(Error::TooShort)
+        ));
+    }
+
+    #[test]
+    fn rejects_artrdm_unsupported_protocol_version() {
+        let mut pkt = artrdm_packet(&[]);
+        pkt[10] = 1; // ProtVerHi
+        assert!(matches!(
+            parse_artrdm(&pkt),
+            Err(Error::UnsupportedVersion(_))
+        ));
+        let mut pkt = artrdm_packet(&[]);
+        pkt[11] = MIN_PROTOCOL_VERSION - 1; // ProtVerLo
+        assert!(matches!(
+            parse_artrdm(&pkt),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn newer_sequence_accepts_zero_on_either_side() {
+        assert!(is_newer_sequence(5, 0)); // incoming 0 disables sequencing
+        assert!(is_newer_sequence(0, 1)); // last 0 means no prior frame yet
+    }
+
+    #[test]
+    fn newer_sequence_handles_wraparound() {
+        assert!(is_newer_sequence(10, 11));
+        assert!(!is_newer_sequence(11, 10));
+        assert!(is_newer_sequence(250, 5)); // wraps past 255 back to 5
+        assert!(!is_newer_sequence(5, 250));
+    }
+}
@@ -4,11 +4,19 @@ use clap_derive::ValueEnum;
 use libc::{
     B38400, BOTHER, BRKINT, CBAUD, CBAUDEX, CLOCAL, CREAD, CRTSCTS, CS8, CSIZE, CSTOPB, ECHO,
     ECHOE, ECHONL, ICANON, ICRNL, IGNBRK, IGNCR, INLCR, ISIG, ISTRIP, IXANY, IXOFF, IXON, ONLCR,
-    OPOST, PARENB, PARMRK, c_int, tcdrain, termios,
+    OPOST, PARENB, PARMRK, TCIFLUSH, c_int, tcdrain, tcflush, termios,
 };
 use std::ffi::CString;
 use std::os::fd::AsFd;
 use std::str::FromStr;
+use std::time::Duration;
+
+/// Minimum time an RDM controller must wait after sending a request before a
+/// responder's reply can appear on the line (ANSI E1.20 responder turnaround).
+const RDM_RESPONDER_TURNAROUND_US: u64 = 176;
+/// RDM packets never exceed 255 message bytes (Message Length, start code
+/// included) + 2 checksum bytes.
+pub(crate) const RDM_MAX_RESPONSE_LEN: usize = 257;
 
 // Good reference for dmx packet / timing
 // https://support.etcconnect.com/ETC/FAQ/DMX_Speed
@@ -43,6 +51,9 @@ enum ResetMode {
 pub(crate) struct Port {
     fd: c_int,
     reset: ResetMode,
+    break_mode: BreakMode,
+    break_us: u32,
+    mab_us: u32,
 }
 
 impl Drop for Port {
@@ -80,6 +91,18 @@ pub enum Mode {
     SetSerial,
 }
 
+/// How the break condition before each DMX frame is generated.
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+pub enum BreakMode {
+    /// Raise/lower the break with TIOCSBRK/TIOCCBRK and busy-wait for the duration.
+    #[default]
+    Ioctl,
+    /// Drop to a slow, arbitrary baud rate (termios2 BOTHER) and clock out a single
+    /// 0x00 byte, so the UART itself generates a correctly timed low period. Use this
+    /// on adapters where TIOCSBRK's timing is unreliable.
+    LowBaud,
+}
+
 impl FromStr for Mode {
     type Err = String;
 
@@ -150,13 +173,59 @@ impl Port {
         Ok(ResetMode::ResetSerial((ss, oldtios)))
     }
 
-    pub fn open(path: &str, mode: Mode) -> Result<Self, std::io::Error> {
+    pub fn open(
+        path: &str,
+        mode: Mode,
+        break_mode: BreakMode,
+        break_us: u32,
+        mab_us: u32,
+    ) -> Result<Self, std::io::Error> {
         let fd = open(path)?;
         let reset = match mode {
             Mode::Termios2 => Self::configure_termios2(fd),
             Mode::SetSerial => Self::configure_set_serial(fd),
         }?;
-        Ok(Port { fd, reset })
+        Ok(Port {
+            fd,
+            reset,
+            break_mode,
+            break_us,
+            mab_us,
+        })
+    }
+
+    /// Generates the break by holding TIOCSBRK for `break_us` and busy-waiting.
+    fn send_break_ioctl(&self) -> Result<(), std::io::Error> {
+        serial::set_brk(self.fd)?;
+        spin_sleep(core::time::Duration::from_micros(self.break_us as u64));
+        serial::clear_break(self.fd)
+    }
+
+    /// Generates the break by dropping to an arbitrary low baud rate and clocking
+    /// out a single 0x00 byte, so the UART produces the low period in hardware -
+    /// see DMX512_BREAK_BITS for the bit-timing math.
+    fn send_break_lowbaud(&self) -> Result<(), std::io::Error> {
+        const DMX512_BREAK_BITS: u32 = 9; // start bit + 8 zero data bits
+        let normal = serial::tcgets2(self.fd)?;
+        let mut tios2 = normal;
+        tios2.c_cflag &= !CBAUD;
+        tios2.c_cflag |= BOTHER;
+        let low_baud = (DMX512_BREAK_BITS * 1_000_000 / self.break_us.max(1)).max(1);
+        tios2.c_ispeed = low_baud;
+        tios2.c_ospeed = low_baud;
+        serial::tcsets2(self.fd, &tios2)?;
+
+        let zero = [0u8];
+        let res =
+            unsafe { libc::write(self.fd, zero.as_ptr() as *const libc::c_void, zero.len()) };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        unsafe {
+            tcdrain(self.fd);
+        }
+
+        serial::tcsets2(self.fd, &normal)
     }
 
     pub fn write(&self, buf: &[u8]) -> Result<usize, std::io::Error> {
@@ -164,12 +233,12 @@ impl Port {
         unsafe {
             tcdrain(self.fd);
         }
-        serial::set_break(self.fd)?;
-        // sleep for 138 us - Break (BRK)
-        spin_sleep(core::time::Duration::from_micros(138));
-        serial::clear_break(self.fd)?;
-        // sleep for 12 us - mark after break (MAB)
-        //spin_sleep(core::time::Duration::from_micros(12));
+        match self.break_mode {
+            BreakMode::Ioctl => self.send_break_ioctl()?,
+            BreakMode::LowBaud => self.send_break_lowbaud()?,
+        }
+        // Mark after break (MAB)
+        spin_sleep(core::time::Duration::from_micros(self.mab_us as u64));
         // Write the buffer to the DMX port - typically 513 bytes (512 channels + 1 start code)
         let res = unsafe { libc::write(self.fd, buf.as_ptr() as *const libc::c_void, buf.len()) };
         if res < 0 {
@@ -178,6 +247,58 @@ impl Port {
             Ok(res as usize)
         }
     }
+
+    /// Sends an already-encoded RDM packet (see `crate::rdm::decode_response` to
+    /// validate one) and returns once the request is on the wire. This does
+    /// *not* wait for or read back the responder's reply -
+    /// that can take up to the controller's configured RDM timeout, and doing
+    /// it here would park whatever reactor called us for that whole window.
+    /// Callers are expected to poll this port's fd for readability (e.g. via
+    /// epoll) and drain the reply with `try_read_rdm` instead.
+    pub fn send_rdm_request(&self, packet: &[u8]) -> Result<(), std::io::Error> {
+        unsafe {
+            tcdrain(self.fd);
+        }
+        match self.break_mode {
+            BreakMode::Ioctl => self.send_break_ioctl()?,
+            BreakMode::LowBaud => self.send_break_lowbaud()?,
+        }
+        spin_sleep(core::time::Duration::from_micros(self.mab_us as u64));
+
+        let res =
+            unsafe { libc::write(self.fd, packet.as_ptr() as *const libc::c_void, packet.len()) };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        unsafe {
+            tcdrain(self.fd);
+        }
+        // Discard anything echoed back onto the line while we were transmitting,
+        // so it isn't mistaken for the start of the responder's reply.
+        unsafe {
+            tcflush(self.fd, TCIFLUSH);
+        }
+
+        spin_sleep(Duration::from_micros(RDM_RESPONDER_TURNAROUND_US));
+        Ok(())
+    }
+
+    /// Non-blocking read of whatever RDM reply bytes are currently available
+    /// into `buf`. Returns `Ok(0)` - not an error - if nothing has arrived yet;
+    /// the caller should retry once the port's fd reports readable again
+    /// (this is why the port is opened `O_NONBLOCK`).
+    pub fn try_read_rdm(&self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        let res = unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if res < 0 {
+            let err = std::io::Error::last_os_error();
+            return if err.kind() == std::io::ErrorKind::WouldBlock {
+                Ok(0)
+            } else {
+                Err(err)
+            };
+        }
+        Ok(res as usize)
+    }
 }
 
 impl AsFd for Port {
@@ -7,13 +7,15 @@ use nix::sys::signalfd::SignalFd;
 use nix::sys::time::{TimeSpec, TimeValLike};
 use nix::sys::timer::{Expiration, TimerSetTimeFlags};
 use nix::sys::timerfd::{ClockId, TimerFd, TimerFlags};
-use num_derive::FromPrimitive;
 use socket2::{Domain, Socket, Type};
 use std::io::Read;
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::time::Duration;
 
+mod artnet;
 mod dmx;
+mod netinfo;
+mod rdm;
 mod serial;
 
 #[derive(Parser, Debug)]
@@ -23,23 +25,57 @@ struct Args {
     #[arg(long, short, default_value = "/dev/ttyUSB0")]
     device: String,
 
-    // IP Address to bind the UDP socket
-    #[arg(long, short, default_value = "0.0.0.0:1337")]
+    // IP Address to bind the UDP socket (6454 is the standard Art-Net port)
+    #[arg(long, short, default_value = "0.0.0.0:6454")]
     bind: SocketAddr,
 
     // Mode of configuration
     #[arg(value_enum, long, short, default_value = "termios2")]
     mode: dmx::Mode,
 
-    // Throttle DMX writes to avoid flooding
+    // Throttle DMX writes to avoid flooding (DMX512 tops out around 44 refreshes/s)
     #[arg(long, short, default_value = "45")]
     throttle: u64,
 
+    // DMX break duration in microseconds (DMX512 minimum is 92us)
+    #[arg(long, default_value = "138")]
+    break_us: u32,
+
+    // Mark-after-break duration in microseconds (DMX512 minimum is 12us)
+    #[arg(long, default_value = "12")]
+    mab_us: u32,
+
+    // How the break condition is generated
+    #[arg(value_enum, long, default_value = "ioctl")]
+    break_mode: dmx::BreakMode,
+
+    // How long to wait for an RDM responder's reply before giving up
+    #[arg(long, default_value = "500")]
+    rdm_timeout_ms: u64,
+
     #[arg(long, short)]
     wait_udp: bool,
 
     #[arg(long, short)]
     debug: bool,
+
+    // Treat incoming UDP payloads as raw DMX slot data instead of Art-Net ArtDMX packets
+    // (only applies to the first configured universe)
+    #[arg(long)]
+    raw: bool,
+
+    // Map an Art-Net universe to an output device, e.g. "0:/dev/ttyUSB0". Repeat for
+    // several universes. If omitted, falls back to a single universe 0 on --device.
+    #[arg(long = "universe")]
+    universes: Vec<String>,
+
+    // Short name advertised in ArtPollReply (max 17 chars)
+    #[arg(long, default_value = "rdmx")]
+    short_name: String,
+
+    // Long name advertised in ArtPollReply (max 63 chars)
+    #[arg(long, default_value = "rdmx Art-Net DMX bridge")]
+    long_name: String,
 }
 
 fn get_domain(socket_addr: SocketAddr) -> Domain {
@@ -49,31 +85,175 @@ fn get_domain(socket_addr: SocketAddr) -> Domain {
     }
 }
 
-#[derive(FromPrimitive)]
-#[repr(u64)]
-enum Event {
-    Signal = 1,
-    UDP = 2,
-    DMX = 3,
-    Tick = 4,
+/// Splits a `"<universe>:<device path>"` mapping into its two parts.
+fn parse_universe_arg(s: &str) -> Result<(u16, String), String> {
+    let (universe, device) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected \"<universe>:<device>\", got \"{}\"", s))?;
+    let universe = universe
+        .parse::<u16>()
+        .map_err(|e| format!("invalid universe \"{}\": {}", universe, e))?;
+    Ok((universe, device.to_string()))
+}
+
+// Event tag packed into the low bits of an epoll event's u64 data payload; the
+// remaining bits carry an index, used by DMX to say which output port fired.
+const EVENT_TAG_BITS: u32 = 4;
+const EVENT_TAG_MASK: u64 = (1 << EVENT_TAG_BITS) - 1;
+const EVENT_SIGNAL: u64 = 0;
+const EVENT_UDP: u64 = 1;
+const EVENT_DMX: u64 = 2;
+const EVENT_TICK: u64 = 3;
+
+fn dmx_event_data(index: usize) -> u64 {
+    EVENT_DMX | ((index as u64) << EVENT_TAG_BITS)
+}
+
+/// One configured Art-Net universe and the serial port it is bridged to.
+struct DmxOutput {
+    port: dmx::Port,
+    universe: u16,
+    data: [u8; 513],
+    last_sequence: u8,
+    next_write: TimeSpec,
+    /// Set while this output's RDM transaction is awaiting (more of) a reply;
+    /// cleared once the reply is complete or the timeout passes. DMX refresh
+    /// for this universe is paused meanwhile, since request and reply share
+    /// the same physical line - the other configured universes keep running.
+    pending_rdm: Option<PendingRdm>,
+}
+
+/// An RDM request that has gone out on the wire and is awaiting a reply,
+/// tracked so the main epoll loop can drain it incrementally instead of
+/// blocking on it.
+struct PendingRdm {
+    peer: SocketAddr,
+    universe: u16,
+    /// Set if the request was a DISC_UNIQUE_BRANCH: its reply doesn't use the
+    /// standard checksum framing, so it must be relayed unvalidated.
+    expect_raw_discovery: bool,
+    response: Vec<u8>,
+    total: usize,
+    deadline: TimeSpec,
+}
+
+/// Decodes (or, for a DISC_UNIQUE_BRANCH reply, passes through unvalidated)
+/// a completed RDM transaction's response and relays it back to the
+/// controller that sent the request.
+fn finish_rdm(pending: PendingRdm, socket: &Socket, args: &Args) {
+    let response = &pending.response[..pending.total];
+    if response.is_empty() {
+        if args.debug {
+            eprintln!("No RDM response on universe {}", pending.universe);
+        }
+        return;
+    }
+    if pending.expect_raw_discovery {
+        if args.debug {
+            eprintln!(
+                "RDM DISC_UNIQUE_BRANCH response ({} bytes) on universe {}",
+                response.len(),
+                pending.universe
+            );
+        }
+    } else {
+        match rdm::decode_response(response) {
+            Ok(decoded) => {
+                if args.debug {
+                    eprintln!(
+                        "RDM {} from {:?} on universe {}",
+                        rdm::command_class_name(decoded.command_class),
+                        decoded.source,
+                        pending.universe
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!(
+                    "Dropping invalid RDM response on universe {}: {}",
+                    pending.universe, e
+                );
+                return;
+            }
+        }
+    }
+    let reply = artnet::build_artrdm(pending.universe, response);
+    if let Err(e) = socket.send_to(&reply, &pending.peer.into()) {
+        eprintln!("Failed to send ArtRdm reply: {}", e);
+    }
+}
+
+/// The epoll timeout to wait with: `NONE` if no RDM transaction is in flight,
+/// otherwise bounded by the soonest transaction's deadline so the reactor
+/// wakes up to time it out even if nothing else happens in the meantime.
+fn rdm_poll_timeout(outputs: &[DmxOutput]) -> std::io::Result<PollTimeout> {
+    let Some(deadline) = outputs
+        .iter()
+        .filter_map(|o| o.pending_rdm.as_ref().map(|p| p.deadline))
+        .min()
+    else {
+        return Ok(PollTimeout::NONE);
+    };
+    let now = nix::time::clock_gettime(nix::time::ClockId::CLOCK_MONOTONIC)?;
+    let remaining_ms = (deadline - now).num_milliseconds().clamp(0, u16::MAX as i64) as u16;
+    Ok(PollTimeout::from(remaining_ms))
 }
 
 fn main() -> std::io::Result<()> {
     let mut exiting = false; // Flag to indicate if the program is exiting
-    let mut dmx_data = [0u8; 513]; // DMX data buffer
+    let mut recv_buf = [0u8; 2048]; // Scratch buffer for incoming UDP datagrams
 
     let args = Args::parse();
     let mut socket = Socket::new(get_domain(args.bind), Type::DGRAM, None)?;
     socket.set_reuse_address(true)?;
     socket.set_nonblocking(true)?;
+    socket.set_broadcast(true)?;
     println!("Binding to: {}", args.bind);
     socket.bind(&args.bind.into())?;
 
-    println!(
-        "Opening DMX device: {} in {:?} mode",
-        args.device, args.mode
-    );
-    let dmx_port = dmx::Port::open(args.device.as_str(), args.mode)?;
+    // Interface/IP detection is best-effort: on hosts with no up, non-loopback
+    // IPv4 interface (containers, loopback-only setups) we still need to start,
+    // just with a less useful ArtPollReply.
+    let (detected_ip, node_mac) = match netinfo::primary_ipv4_interface() {
+        Ok((ifname, ip)) => (ip, netinfo::mac_address(&ifname).unwrap_or([0u8; 6])),
+        Err(_) => (Ipv4Addr::UNSPECIFIED, [0u8; 6]),
+    };
+    let node_ip = match args.bind {
+        SocketAddr::V4(v4) if !v4.ip().is_unspecified() => *v4.ip(),
+        _ => detected_ip,
+    };
+
+    let universe_mappings = if args.universes.is_empty() {
+        vec![(0u16, args.device.clone())]
+    } else {
+        args.universes
+            .iter()
+            .map(|s| parse_universe_arg(s).map_err(std::io::Error::other))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let mut outputs = Vec::with_capacity(universe_mappings.len());
+    for (universe, device) in universe_mappings {
+        println!(
+            "Opening DMX device: {} for universe {} in {:?} mode",
+            device, universe, args.mode
+        );
+        outputs.push(DmxOutput {
+            port: dmx::Port::open(
+                device.as_str(),
+                args.mode,
+                args.break_mode,
+                args.break_us,
+                args.mab_us,
+            )?,
+            universe,
+            data: [0u8; 513],
+            last_sequence: 0,
+            next_write: TimeSpec::zero(),
+            pending_rdm: None,
+        });
+    }
+
     let mask = SigSet::from_iter([Signal::SIGINT, Signal::SIGTERM, Signal::SIGUSR1]);
     sigprocmask(SigmaskHow::SIG_BLOCK, Some(&mask), None)?;
 
@@ -85,20 +265,22 @@ fn main() -> std::io::Result<()> {
     let epoll = Epoll::new(EpollCreateFlags::empty())?;
     epoll.add(
         &sigfd,
-        EpollEvent::new(EpollFlags::EPOLLIN, Event::Signal as u64),
-    )?;
-    epoll.add(
-        &socket,
-        EpollEvent::new(EpollFlags::EPOLLIN, Event::UDP as u64),
-    )?;
-    epoll.add(
-        &dmx_port,
-        EpollEvent::new(EpollFlags::EPOLLOUT, Event::DMX as u64),
-    )?;
-    epoll.add(
-        &ticker,
-        EpollEvent::new(EpollFlags::EPOLLIN, Event::Tick as u64),
+        EpollEvent::new(EpollFlags::EPOLLIN, EVENT_SIGNAL),
     )?;
+    epoll.add(&socket, EpollEvent::new(EpollFlags::EPOLLIN, EVENT_UDP))?;
+    for (index, output) in outputs.iter().enumerate() {
+        // EPOLLIN lets an in-flight RDM reply be drained as it trickles in;
+        // EPOLLOUT is the existing (almost-always-ready) trigger for the
+        // throttled periodic DMX refresh.
+        epoll.add(
+            &output.port,
+            EpollEvent::new(
+                EpollFlags::EPOLLOUT | EpollFlags::EPOLLIN,
+                dmx_event_data(index),
+            ),
+        )?;
+    }
+    epoll.add(&ticker, EpollEvent::new(EpollFlags::EPOLLIN, EVENT_TICK))?;
 
     let mut event_buffer = [EpollEvent::empty(); 10];
 
@@ -110,23 +292,27 @@ fn main() -> std::io::Result<()> {
     let mut last_dmx_delta = 0;
     let mut first_udp_packet = false;
 
-    let mut dmx_write_throttle = TimeSpec::zero();
-
     while !exiting {
-        let n = epoll.wait(&mut event_buffer, PollTimeout::NONE)?;
+        let n = epoll.wait(&mut event_buffer, rdm_poll_timeout(&outputs)?)?;
+
+        // Finalize any RDM transaction whose deadline has passed, regardless
+        // of what woke us up (a timeout, or an unrelated fd firing).
+        let now = nix::time::clock_gettime(nix::time::ClockId::CLOCK_MONOTONIC)?;
+        for output in outputs.iter_mut() {
+            if output.pending_rdm.as_ref().is_some_and(|p| now >= p.deadline) {
+                if let Some(pending) = output.pending_rdm.take() {
+                    finish_rdm(pending, &socket, &args);
+                }
+            }
+        }
+
         for event in event_buffer[..n].iter().as_slice() {
             if exiting {
                 break;
             }
-            let event = match num::FromPrimitive::from_u64(event.data()) {
-                Some(val) => val,
-                None => {
-                    println!("Received unknown event number: {:?}", event.data());
-                    continue;
-                }
-            };
-            match event {
-                Event::Signal => {
+            let tag = event.data() & EVENT_TAG_MASK;
+            match tag {
+                EVENT_SIGNAL => {
                     if let Some(info) = sigfd.read_signal()? {
                         match (info.ssi_signo as i32).try_into() {
                             Ok(Signal::SIGINT) => {
@@ -149,35 +335,225 @@ fn main() -> std::io::Result<()> {
                         }
                     }
                 }
-                Event::UDP => {
+                EVENT_UDP => {
                     // This is a non-blocking socket
                     // We need to drain all buffered frames to catch up
-                    while let Ok(_size) = socket.read(&mut dmx_data[1..]) {
+                    while let Ok((size, peer)) = socket.recv_from(&mut recv_buf) {
                         udp_frames += 1; // Increment UDP frame count
                         first_udp_packet = true;
+                        if let Ok(artrdm) = artnet::parse_artrdm(&recv_buf[..size]) {
+                            let Some(output) =
+                                outputs.iter_mut().find(|o| o.universe == artrdm.universe)
+                            else {
+                                if args.debug {
+                                    eprintln!(
+                                        "Dropping ArtRdm for unconfigured universe {}",
+                                        artrdm.universe
+                                    );
+                                }
+                                continue;
+                            };
+                            if output.pending_rdm.is_some() {
+                                if args.debug {
+                                    eprintln!(
+                                        "RDM transaction already in flight on universe {}, dropping request",
+                                        artrdm.universe
+                                    );
+                                }
+                                continue;
+                            }
+                            let expect_raw_discovery = match rdm::decode_response(artrdm.rdm_packet)
+                            {
+                                Ok(decoded) => {
+                                    if args.debug {
+                                        eprintln!(
+                                            "RDM {} for {:?} on universe {}",
+                                            rdm::command_class_name(decoded.command_class),
+                                            decoded.destination,
+                                            artrdm.universe
+                                        );
+                                    }
+                                    decoded.command_class == rdm::CC_DISCOVERY_COMMAND
+                                        && decoded.parameter_id == rdm::PID_DISC_UNIQUE_BRANCH
+                                }
+                                Err(e) => {
+                                    if args.debug {
+                                        eprintln!(
+                                            "Dropping malformed ArtRdm request on universe {}: {}",
+                                            artrdm.universe, e
+                                        );
+                                    }
+                                    continue;
+                                }
+                            };
+                            let Some(peer_addr) = peer.as_socket() else {
+                                if args.debug {
+                                    eprintln!(
+                                        "No reply address for RDM request on universe {}",
+                                        artrdm.universe
+                                    );
+                                }
+                                continue;
+                            };
+                            if let Err(e) = output.port.send_rdm_request(artrdm.rdm_packet) {
+                                eprintln!(
+                                    "RDM transmit failed on universe {}: {}",
+                                    artrdm.universe, e
+                                );
+                                continue;
+                            }
+                            let now =
+                                nix::time::clock_gettime(nix::time::ClockId::CLOCK_MONOTONIC)?;
+                            output.pending_rdm = Some(PendingRdm {
+                                peer: peer_addr,
+                                universe: artrdm.universe,
+                                expect_raw_discovery,
+                                response: vec![0u8; dmx::RDM_MAX_RESPONSE_LEN],
+                                total: 0,
+                                deadline: now
+                                    + TimeSpec::from(Duration::from_millis(args.rdm_timeout_ms)),
+                            });
+                            continue;
+                        }
+                        if artnet::is_artpoll(&recv_buf[..size]) {
+                            let universes: Vec<u16> = outputs.iter().map(|o| o.universe).collect();
+                            if universes.len() > artnet::MAX_REPLY_PORTS && args.debug {
+                                eprintln!(
+                                    "ArtPollReply only reports {} of {} configured universes",
+                                    artnet::MAX_REPLY_PORTS,
+                                    universes.len()
+                                );
+                            }
+                            let reply = artnet::build_poll_reply(&artnet::NodeInfo {
+                                ip: node_ip,
+                                mac: node_mac,
+                                short_name: &args.short_name,
+                                long_name: &args.long_name,
+                                universes: &universes,
+                            });
+                            let broadcast =
+                                SocketAddr::new(IpAddr::V4(Ipv4Addr::BROADCAST), artnet::PORT);
+                            if let Err(e) = socket.send_to(&reply, &broadcast.into()) {
+                                eprintln!("Failed to send ArtPollReply: {}", e);
+                            }
+                            continue;
+                        }
+                        if args.raw {
+                            if let Some(output) = outputs.first_mut() {
+                                let n = size.min(output.data.len() - 1);
+                                output.data[1..1 + n].copy_from_slice(&recv_buf[..n]);
+                            }
+                            continue;
+                        }
+                        match artnet::parse_artdmx(&recv_buf[..size]) {
+                            Ok(artdmx) => {
+                                let Some(output) =
+                                    outputs.iter_mut().find(|o| o.universe == artdmx.universe)
+                                else {
+                                    if args.debug {
+                                        eprintln!(
+                                            "Dropping ArtDmx for unconfigured universe {}",
+                                            artdmx.universe
+                                        );
+                                    }
+                                    continue;
+                                };
+                                if !artnet::is_newer_sequence(output.last_sequence, artdmx.sequence)
+                                {
+                                    continue;
+                                }
+                                if args.debug {
+                                    eprintln!(
+                                        "ArtDmx universe {} from physical port {}",
+                                        artdmx.universe, artdmx.physical
+                                    );
+                                }
+                                output.last_sequence = artdmx.sequence;
+                                let n = artdmx.data.len().min(output.data.len() - 1);
+                                output.data[1..1 + n].copy_from_slice(&artdmx.data[..n]);
+                            }
+                            Err(e) => {
+                                if args.debug {
+                                    eprintln!("Dropping non-ArtDmx UDP packet: {}", e);
+                                }
+                            }
+                        }
                     }
                 }
-                Event::DMX => {
+                EVENT_DMX => {
+                    let index = (event.data() >> EVENT_TAG_BITS) as usize;
+                    let output = &mut outputs[index];
+                    if output.pending_rdm.is_some() {
+                        // Drain whatever reply bytes are available; don't
+                        // also treat this wakeup as a periodic-write trigger
+                        // while the line is busy with an RDM transaction.
+                        let outcome = {
+                            let port = &output.port;
+                            let pending = output.pending_rdm.as_mut().unwrap();
+                            port.try_read_rdm(&mut pending.response[pending.total..])
+                        };
+                        match outcome {
+                            Ok(0) => {}
+                            Ok(n) => {
+                                let pending = output.pending_rdm.as_mut().unwrap();
+                                pending.total += n;
+                                // A standard-framed reply (anything but a
+                                // DISC_UNIQUE_BRANCH response) carries its own
+                                // length in the header, so it can complete as
+                                // soon as that many bytes are in - no need to
+                                // wait out the full timeout for a ~20-40 byte
+                                // GET/SET ack. DISC_UNIQUE_BRANCH has no fixed
+                                // length to predict, so it still runs to the
+                                // buffer cap or the deadline.
+                                let done = if pending.expect_raw_discovery {
+                                    pending.total >= pending.response.len()
+                                } else {
+                                    match rdm::expected_len(&pending.response[..pending.total]) {
+                                        Some(expected) => {
+                                            pending.total >= expected.min(pending.response.len())
+                                        }
+                                        None => pending.total >= pending.response.len(),
+                                    }
+                                };
+                                if done {
+                                    if let Some(pending) = output.pending_rdm.take() {
+                                        finish_rdm(pending, &socket, &args);
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!(
+                                    "RDM read failed on universe {}: {}",
+                                    output.universe, e
+                                );
+                                output.pending_rdm = None;
+                            }
+                        }
+                        continue;
+                    }
                     if !first_udp_packet && args.wait_udp {
                         continue;
                     }
                     let now = nix::time::clock_gettime(nix::time::ClockId::CLOCK_MONOTONIC)?;
-                    if now - dmx_write_throttle
+                    if now - output.next_write
                         < TimeSpec::from(Duration::from_millis(1000 / args.throttle))
                     {
                         // Throttle DMX writes to avoid flooding
                         continue;
                     }
                     // Write DMX data to the port
-                    if let Err(e) = dmx_port.write(&dmx_data) {
-                        eprintln!("Failed to write DMX data: {}", e);
+                    if let Err(e) = output.port.write(&output.data) {
+                        eprintln!(
+                            "Failed to write DMX data for universe {}: {}",
+                            output.universe, e
+                        );
                         exiting = true;
                     }
                     dmx_frames += 1; // Increment DMX frame count
-                    dmx_write_throttle =
+                    output.next_write =
                         nix::time::clock_gettime(nix::time::ClockId::CLOCK_MONOTONIC)?;
                 }
-                Event::Tick => {
+                EVENT_TICK => {
                     // Handle timer tick event
                     let _ = ticker.wait();
                     let udp_frames_delta = udp_frames - last_udp_frames;
@@ -193,6 +569,9 @@ fn main() -> std::io::Result<()> {
                     last_udp_frames = udp_frames;
                     last_dmx_frames = dmx_frames;
                 }
+                _ => {
+                    println!("Received unknown event tag: {:?}", tag);
+                }
             }
         }
     }
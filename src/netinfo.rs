@@ -0,0 +1,54 @@
+// Minimal local network interface discovery, used to fill in the
+// ArtPollReply IP/MAC fields without pulling in a netlink crate.
+
+use std::ffi::CStr;
+use std::fs;
+use std::io;
+use std::net::Ipv4Addr;
+
+/// Returns the name and IPv4 address of the first up, non-loopback interface.
+pub fn primary_ipv4_interface() -> io::Result<(String, Ipv4Addr)> {
+    let mut addrs: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut addrs) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut cursor = addrs;
+    let mut found = None;
+    while !cursor.is_null() {
+        let ifa = unsafe { &*cursor };
+        cursor = ifa.ifa_next;
+
+        if ifa.ifa_addr.is_null() {
+            continue;
+        }
+        let flags = ifa.ifa_flags as i32;
+        if flags & libc::IFF_LOOPBACK != 0 || flags & libc::IFF_UP == 0 {
+            continue;
+        }
+        if unsafe { (*ifa.ifa_addr).sa_family as i32 } != libc::AF_INET {
+            continue;
+        }
+        let sin = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in) };
+        let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+        let name = unsafe { CStr::from_ptr(ifa.ifa_name) }
+            .to_string_lossy()
+            .into_owned();
+        found = Some((name, ip));
+        break;
+    }
+
+    unsafe { libc::freeifaddrs(addrs) };
+    found.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no usable network interface"))
+}
+
+/// Reads the MAC address of a named interface out of sysfs.
+pub fn mac_address(ifname: &str) -> io::Result<[u8; 6]> {
+    let raw = fs::read_to_string(format!("/sys/class/net/{}/address", ifname))?;
+    let mut mac = [0u8; 6];
+    for (slot, part) in mac.iter_mut().zip(raw.trim().split(':')) {
+        *slot = u8::from_str_radix(part, 16)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    }
+    Ok(mac)
+}
@@ -0,0 +1,240 @@
+// RDM (ANSI E1.20) packet decoding and validation.
+// Reference: https://tsp.esta.org/tsp/documents/docs/ANSI-ESTA_E1-20_2010.pdf
+
+use std::fmt;
+
+/// DMX start code that marks a slot-0 byte as carrying an RDM message.
+pub const START_CODE: u8 = 0xCC;
+pub const SUB_START_CODE: u8 = 0x01;
+
+pub const CC_DISCOVERY_COMMAND: u8 = 0x10;
+pub const CC_DISCOVERY_COMMAND_RESPONSE: u8 = 0x11;
+pub const CC_GET_COMMAND: u8 = 0x20;
+pub const CC_GET_COMMAND_RESPONSE: u8 = 0x21;
+pub const CC_SET_COMMAND: u8 = 0x30;
+pub const CC_SET_COMMAND_RESPONSE: u8 = 0x31;
+
+/// Parameter ID for the discovery-only DISC_UNIQUE_BRANCH command: its reply
+/// uses a different, non-checksummed line encoding (see `decode_response`'s
+/// doc comment) specifically so colliding responders can still be told apart.
+pub const PID_DISC_UNIQUE_BRANCH: u16 = 0x0001;
+
+/// Bytes from StartCode up to and including Parameter Data Length.
+const HEADER_LEN: usize = 24;
+const CHECKSUM_LEN: usize = 2;
+
+/// A 48-bit RDM device UID: 2-byte ESTA manufacturer ID, 4-byte device ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uid(pub [u8; 6]);
+
+impl Uid {
+    pub fn from_slice(b: &[u8]) -> Option<Uid> {
+        let mut uid = [0u8; 6];
+        uid.copy_from_slice(b.get(..6)?);
+        Some(Uid(uid))
+    }
+}
+
+fn checksum(pkt: &[u8]) -> u16 {
+    pkt.iter().fold(0u16, |sum, &b| sum.wrapping_add(b as u16))
+}
+
+/// Names a command class byte for debug logging; unrecognized values (e.g.
+/// manufacturer-specific classes) are all named "UNKNOWN".
+pub fn command_class_name(cc: u8) -> &'static str {
+    match cc {
+        CC_DISCOVERY_COMMAND => "DISCOVERY_COMMAND",
+        CC_DISCOVERY_COMMAND_RESPONSE => "DISCOVERY_COMMAND_RESPONSE",
+        CC_GET_COMMAND => "GET_COMMAND",
+        CC_GET_COMMAND_RESPONSE => "GET_COMMAND_RESPONSE",
+        CC_SET_COMMAND => "SET_COMMAND",
+        CC_SET_COMMAND_RESPONSE => "SET_COMMAND_RESPONSE",
+        _ => "UNKNOWN",
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub source: Uid,
+    pub destination: Uid,
+    pub transaction_number: u8,
+    pub response_type: u8,
+    pub command_class: u8,
+    pub parameter_id: u16,
+    pub parameter_data: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    TooShort,
+    BadStartCode,
+    ChecksumMismatch,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::TooShort => write!(f, "packet shorter than the RDM header"),
+            DecodeError::BadStartCode => write!(f, "missing RDM start code / sub-start code"),
+            DecodeError::ChecksumMismatch => write!(f, "RDM checksum mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Returns the total on-wire length (message plus trailing checksum) of a
+/// standard-framed RDM message, as soon as enough of it - the first three
+/// bytes - has arrived to read the Message Length field (`buf[2]`). Returns
+/// `None` if `buf` is still too short to know yet, or if the field read back
+/// is already too small to hold the fixed header (i.e. noise or a corrupt
+/// reply rather than a real length) - either way the caller should keep
+/// reading until its buffer cap or deadline, the same as if this returned
+/// nothing at all, rather than act on an unusable length. Does not apply to
+/// a DISC_UNIQUE_BRANCH reply, which has no fixed length to predict.
+pub fn expected_len(buf: &[u8]) -> Option<usize> {
+    let message_len = *buf.get(2)? as usize;
+    if message_len < HEADER_LEN {
+        return None;
+    }
+    Some(message_len + CHECKSUM_LEN)
+}
+
+/// Decodes a responder's reply, as read back off the DMX line.
+///
+/// This assumes the standard `0xCC,0x01` start code framing with a trailing
+/// 16-bit checksum, which every RDM response uses *except* the reply to
+/// DISC_UNIQUE_BRANCH (`PID_DISC_UNIQUE_BRANCH`): that one is a 0xFE* preamble
+/// plus 0xAA delimiter followed by a non-checksummed, bit-interleaved UID
+/// encoding instead. Callers that sent a DISC_UNIQUE_BRANCH request must not
+/// run the reply through this function - relay it unvalidated instead.
+pub fn decode_response(buf: &[u8]) -> Result<Response, DecodeError> {
+    if buf.len() < HEADER_LEN + CHECKSUM_LEN {
+        return Err(DecodeError::TooShort);
+    }
+    if buf[0] != START_CODE || buf[1] != SUB_START_CODE {
+        return Err(DecodeError::BadStartCode);
+    }
+    let message_len = buf[2] as usize;
+    if message_len < HEADER_LEN || buf.len() < message_len + CHECKSUM_LEN {
+        return Err(DecodeError::TooShort);
+    }
+    let given = u16::from_be_bytes([buf[message_len], buf[message_len + 1]]);
+    if given != checksum(&buf[..message_len]) {
+        return Err(DecodeError::ChecksumMismatch);
+    }
+    let param_len = buf[23] as usize;
+    if HEADER_LEN + param_len > message_len {
+        return Err(DecodeError::TooShort);
+    }
+    Ok(Response {
+        destination: Uid::from_slice(&buf[3..9]).ok_or(DecodeError::TooShort)?,
+        source: Uid::from_slice(&buf[9..15]).ok_or(DecodeError::TooShort)?,
+        transaction_number: buf[15],
+        response_type: buf[16],
+        command_class: buf[20],
+        parameter_id: u16::from_be_bytes([buf[21], buf[22]]),
+        parameter_data: buf[HEADER_LEN..HEADER_LEN + param_len].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal, well-formed RDM response with `param_len` bytes of
+    /// parameter data and a correct trailing checksum.
+    fn make_response(param_len: u8) -> Vec<u8> {
+        let message_len = HEADER_LEN + param_len as usize;
+        let mut pkt = vec![0u8; message_len + CHECKSUM_LEN];
+        pkt[0] = START_CODE;
+        pkt[1] = SUB_START_CODE;
+        pkt[2] = message_len as u8;
+        pkt[20] = CC_GET_COMMAND_RESPONSE;
+        pkt[23] = param_len;
+        let sum = checksum(&pkt[..message_len]);
+        pkt[message_len..message_len + CHECKSUM_LEN].copy_from_slice(&sum.to_be_bytes());
+        pkt
+    }
+
+    #[test]
+    fn decodes_well_formed_response() {
+        let pkt = make_response(2);
+        let resp = decode_response(&pkt).unwrap();
+        assert_eq!(resp.command_class, CC_GET_COMMAND_RESPONSE);
+        assert_eq!(resp.parameter_data.len(), 2);
+    }
+
+    #[test]
+    fn expected_len_reads_message_len_once_available() {
+        let pkt = make_response(2);
+        assert_eq!(expected_len(&pkt[..2]), None);
+        assert_eq!(expected_len(&pkt[..3]), Some(pkt.len()));
+        assert_eq!(expected_len(&pkt), Some(pkt.len()));
+    }
+
+    #[test]
+    fn expected_len_ignores_a_message_len_smaller_than_the_header() {
+        // A Message Length field that can't even hold the fixed header is
+        // noise/corruption, not a real (short) reply - returning None here
+        // means the caller keeps reading instead of cutting the transaction
+        // short on a bogus length.
+        let mut pkt = make_response(2);
+        pkt[2] = (HEADER_LEN - 1) as u8;
+        assert_eq!(expected_len(&pkt[..3]), None);
+    }
+
+    #[test]
+    fn rejects_message_len_shorter_than_header() {
+        // message_len < HEADER_LEN must be rejected before it's used to index
+        // into the buffer for the checksum - this is the slice panic fixed in
+        // a follow-up after RDM support first landed.
+        let mut pkt = make_response(0);
+        pkt[2] = (HEADER_LEN - 1) as u8;
+        assert!(matches!(decode_response(&pkt), Err(DecodeError::TooShort)));
+    }
+
+    #[test]
+    fn rejects_param_len_overflowing_message_len() {
+        // A forged param_len that would read past message_len must be
+        // rejected instead of panicking on the final slice. Recompute the
+        // checksum after forging param_len so the checksum check (which runs
+        // first) doesn't mask the param_len check this test is about.
+        let mut pkt = make_response(2);
+        let message_len = pkt[2] as usize;
+        pkt[23] = 0xff;
+        let sum = checksum(&pkt[..message_len]);
+        pkt[message_len..message_len + CHECKSUM_LEN].copy_from_slice(&sum.to_be_bytes());
+        assert!(matches!(decode_response(&pkt), Err(DecodeError::TooShort)));
+    }
+
+    #[test]
+    fn rejects_short_buffer() {
+        let pkt = make_response(0);
+        assert!(matches!(
+            decode_response(&pkt[..HEADER_LEN]),
+            Err(DecodeError::TooShort)
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_start_code() {
+        let mut pkt = make_response(0);
+        pkt[0] = 0x00;
+        assert!(matches!(
+            decode_response(&pkt),
+            Err(DecodeError::BadStartCode)
+        ));
+    }
+
+    #[test]
+    fn rejects_checksum_mismatch() {
+        let mut pkt = make_response(0);
+        let last = pkt.len() - 1;
+        pkt[last] ^= 0xff;
+        assert!(matches!(
+            decode_response(&pkt),
+            Err(DecodeError::ChecksumMismatch)
+        ));
+    }
+}